@@ -1,9 +1,9 @@
 use futures::TryFutureExt;
 use log::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::pool::PoolConnection;
-use sqlx::{Connect, Connection, Database};
-use tide::{Error, IntoResponse, Request, Response, ResultExt};
+use sqlx::{Connect, Connection, Database, Transaction};
+use tide::{Request, Response};
 
 use crate::api::model::*;
 use crate::api::util::*;
@@ -25,86 +25,81 @@ impl From<Profile> for ProfileResponseBody {
 ///
 /// [Get Profile](https://github.com/gothinkster/realworld/tree/master/api#get-profile)
 pub async fn get_profile<DB>(
-    req: Request<impl Db<Conn = PoolConnection<DB>>>,
+    req: Request<impl Db<Conn = PoolConnection<DB>> + TokenVerifier>,
 ) -> Response
-    where DB: Connect + ProvideData + Database
+    where DB: Connect + Database, for<'c> Transaction<'c, DB>: ProvideData
 {
-    async move {
-        let authenticated = optionally_auth(&req).transpose()?;
-
-        let leader_username = req.param::<String>("username").client_err()?;
-        debug!("Searching for profile {}", leader_username);
-
-        let state = req.state();
-        let mut tx = state
-            .conn()
-            .and_then(Connection::begin)
-            .await
-            .server_err()?;
-
-        let leader = tx.get_profile_by_username(&leader_username).await?;
-
-        debug!("Found profile for {}", leader_username);
-
-        let is_following = if let Some((follower_id, _)) = authenticated {
-            tx.is_following(leader.user_id, follower_id).await?
-        } else {
-            false
-        };
-        tx.commit().await.server_err()?;
-
-        let resp = to_json_response(&ProfileResponseBody {
-            profile: Profile::from(leader).following(is_following),
-        })?;
-        Ok::<_, Error>(resp)
-    }
-    .await
-    .unwrap_or_else(IntoResponse::into_response)
+    get_profile_inner(req).await.unwrap_or_else(Into::into)
+}
+
+async fn get_profile_inner<DB>(
+    req: Request<impl Db<Conn = PoolConnection<DB>> + TokenVerifier>,
+) -> Result<Response, ApiError>
+    where DB: Connect + Database, for<'c> Transaction<'c, DB>: ProvideData
+{
+    let authenticated = optionally_auth(&req).transpose()?;
+
+    let leader_username = req
+        .param::<String>("username")
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    debug!("Searching for profile {}", leader_username);
+
+    let state = req.state();
+    let mut tx = state.conn().and_then(Connection::begin).await?;
+
+    let leader = tx.get_profile_by_username(&leader_username).await?;
+
+    debug!("Found profile for {}", leader_username);
+
+    let is_following = if let Some((follower_id, _)) = authenticated {
+        tx.is_following(leader.user_id, follower_id).await?
+    } else {
+        false
+    };
+    tx.commit().await?;
+
+    to_json_response(&ProfileResponseBody {
+        profile: Profile::from(leader).following(is_following),
+    })
 }
 
 /// Follow a user
 ///
 /// [Follow User](https://github.com/gothinkster/realworld/tree/master/api#follow-user)
 pub async fn follow_user<DB>(
-    req: Request<impl Db<Conn = PoolConnection<DB>>>,
+    req: Request<impl Db<Conn = PoolConnection<DB>> + TokenVerifier>,
 ) -> Response
-    where DB: Connect + ProvideData + Database
+    where DB: Connect + Database, for<'c> Transaction<'c, DB>: ProvideData
 {
-    should_follow(req, true)
-        .await
-        .unwrap_or_else(IntoResponse::into_response)
+    should_follow(req, true).await.unwrap_or_else(Into::into)
 }
 
 /// Stop following a user
 ///
 /// [Unfollow User](https://github.com/gothinkster/realworld/tree/master/api#unfollow-user)
 pub async fn unfollow_user<DB>(
-    req: Request<impl Db<Conn = PoolConnection<DB>>>,
+    req: Request<impl Db<Conn = PoolConnection<DB>> + TokenVerifier>,
 ) -> Response
-    where DB: Connect + ProvideData + Database
+    where DB: Connect + Database, for<'c> Transaction<'c, DB>: ProvideData
 {
-    should_follow(req, false)
-        .await
-        .unwrap_or_else(IntoResponse::into_response)
+    should_follow(req, false).await.unwrap_or_else(Into::into)
 }
 
 /// Adds or removes a following relationship
 async fn should_follow<DB>(
-    req: Request<impl Db<Conn = PoolConnection<DB>>>,
+    req: Request<impl Db<Conn = PoolConnection<DB>> + TokenVerifier>,
     should_follow: bool,
-) -> tide::Result<Response>
-    where DB: Connect + ProvideData + Database
+) -> Result<Response, ApiError>
+    where DB: Connect + Database, for<'c> Transaction<'c, DB>: ProvideData
 {
     let (user_id, _) = extract_and_validate_token(&req)?;
 
-    let leader_username = req.param::<String>("username").client_err()?;
+    let leader_username = req
+        .param::<String>("username")
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
 
     let state = req.state();
-    let mut tx = state
-        .conn()
-        .and_then(Connection::begin)
-        .await
-        .server_err()?;
+    let mut tx = state.conn().and_then(Connection::begin).await?;
 
     let leader_ent = tx.get_profile_by_username(&leader_username).await?;
 
@@ -119,10 +114,98 @@ async fn should_follow<DB>(
         }
     }?;
 
-    tx.commit().await.server_err()?;
+    tx.commit().await?;
 
     let profile = Profile::from(leader_ent).following(should_follow);
 
-    let resp = to_json_response(&ProfileResponseBody::from(profile))?;
-    Ok(resp)
+    to_json_response(&ProfileResponseBody::from(profile))
+}
+
+#[derive(Serialize)]
+struct ProfileListResponseBody {
+    profiles: Vec<Profile>,
+    profiles_count: i64,
+}
+
+#[derive(Deserialize)]
+struct Pagination {
+    #[serde(default)]
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: Option<i64>,
+}
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+/// List the profiles that follow a user
+///
+/// GET /api/profiles/:username/followers
+pub async fn get_followers<DB>(
+    req: Request<impl Db<Conn = PoolConnection<DB>> + TokenVerifier>,
+) -> Response
+    where DB: Connect + Database, for<'c> Transaction<'c, DB>: ProvideData
+{
+    list_relationship(req, Relationship::Followers)
+        .await
+        .unwrap_or_else(Into::into)
+}
+
+/// List the profiles a user follows
+///
+/// GET /api/profiles/:username/following
+pub async fn get_following<DB>(
+    req: Request<impl Db<Conn = PoolConnection<DB>> + TokenVerifier>,
+) -> Response
+    where DB: Connect + Database, for<'c> Transaction<'c, DB>: ProvideData
+{
+    list_relationship(req, Relationship::Following)
+        .await
+        .unwrap_or_else(Into::into)
+}
+
+enum Relationship {
+    Followers,
+    Following,
+}
+
+/// Shared pagination + lookup for the follower/following listings
+async fn list_relationship<DB>(
+    req: Request<impl Db<Conn = PoolConnection<DB>> + TokenVerifier>,
+    relationship: Relationship,
+) -> Result<Response, ApiError>
+    where DB: Connect + Database, for<'c> Transaction<'c, DB>: ProvideData
+{
+    let viewer_id = optionally_auth(&req)
+        .transpose()?
+        .map(|(user_id, _)| user_id);
+
+    let username = req
+        .param::<String>("username")
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+
+    let Pagination { limit, offset } = req
+        .query()
+        .map_err(|e| ApiError::Validation(e.to_string()))?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = offset.unwrap_or(0).max(0);
+
+    let state = req.state();
+    let mut tx = state.conn().and_then(Connection::begin).await?;
+
+    // Same 404-on-unknown-username behavior as get_profile/should_follow above, rather than
+    // silently returning an empty page for a nonexistent user.
+    tx.get_profile_by_username(&username).await?;
+
+    let (profiles, profiles_count) = match relationship {
+        Relationship::Followers => tx.get_followers(&username, viewer_id, limit, offset).await?,
+        Relationship::Following => tx.get_following(&username, viewer_id, limit, offset).await?,
+    };
+
+    tx.commit().await?;
+
+    to_json_response(&ProfileListResponseBody {
+        profiles,
+        profiles_count,
+    })
 }
@@ -1,22 +1,53 @@
 use log::*;
-use tide::{Body, Error, Request, Response, StatusCode};
+use tide::{Body, IntoResponse, Request, Response, StatusCode};
 use serde::{Deserialize, Serialize};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
 
 use crate::db::model::ProvideError;
 
-/// The signing key used to mint auth tokens
-pub const SECRET_KEY: &str = "this-is-the-most-secret-key-ever-secreted";
-
 #[derive(Serialize, Deserialize)]
 pub struct TokenClaims {
     pub sub: i32,
     pub exp: i64,
+    // `#[serde(default)]` so tokens minted before these claims existed still decode
+    #[serde(default)]
+    pub iat: i64,
+    #[serde(default)]
+    pub nbf: i64,
+}
+
+/// A single verification key, selected by the token's `kid` header
+pub struct VerificationKey {
+    pub algorithm: Algorithm,
+    pub key: DecodingKey<'static>,
+}
+
+/// Supplies the keys and validation rules used to verify inbound JWTs
+pub trait TokenVerifier {
+    /// Look up the verification key for the token's `kid` header, if any
+    ///
+    /// A `None` `kid` selects the deployment's default key.
+    fn verification_key(&self, kid: Option<&str>) -> Option<&VerificationKey>;
+
+    /// The issuer/audience/leeway rules to apply on top of key verification
+    fn validation(&self) -> &Validation;
 }
 
-/// Retrieve the authorization header from a Request
-fn get_auth_header<T>(req: &Request<T>) -> Option<&str> {
-    // TODO: It is possible the user will provide multiple auth headers, we should try all of them
-    req.header("Authorization").map(|h| h.last().as_str())
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    #[error("no key found for this token")]
+    UnknownKey,
+    #[error("token is not valid yet")]
+    NotYetValid,
+    #[error(transparent)]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+}
+
+/// Retrieve every `Authorization` header value present on a Request
+fn get_auth_headers<T>(req: &Request<T>) -> impl Iterator<Item = &str> {
+    req.header("Authorization")
+        .into_iter()
+        .flat_map(|values| values.iter().map(|v| v.as_str()))
 }
 
 /// Extract the JWT token from a header string
@@ -24,15 +55,32 @@ fn parse_token(header: &str) -> String {
     header.splitn(2, ' ').nth(1).unwrap_or_default().to_owned()
 }
 
-/// Authorize a JWT returning the user_id
-fn authorize_token(token: &str) -> jsonwebtoken::errors::Result<i32> {
-    let data = jsonwebtoken::decode::<TokenClaims>(
-        token,
-        SECRET_KEY.as_ref(),
-        &jsonwebtoken::Validation::default(),
-    )?;
+/// Authorize a JWT against the state's [`TokenVerifier`], returning its claims
+fn authorize_token<S: TokenVerifier>(
+    state: &S,
+    token: &str,
+) -> Result<TokenClaims, AuthError> {
+    let kid = jsonwebtoken::decode_header(token)?.kid;
+
+    let verification_key = state
+        .verification_key(kid.as_deref())
+        .ok_or(AuthError::UnknownKey)?;
+
+    let mut validation = state.validation().clone();
+    validation.algorithms = vec![verification_key.algorithm];
+
+    let data =
+        jsonwebtoken::decode::<TokenClaims>(token, &verification_key.key, &validation)?;
+
+    if data.claims.nbf > now() {
+        return Err(AuthError::NotYetValid);
+    }
 
-    Ok(data.claims.sub)
+    Ok(data.claims)
+}
+
+fn now() -> i64 {
+    jsonwebtoken::get_current_timestamp() as i64
 }
 
 /// Validate an auth token if one is present in the request
@@ -42,7 +90,7 @@ fn authorize_token(token: &str) -> jsonwebtoken::errors::Result<i32> {
 /// 1. No authorization header present -> None
 /// 2. Invalid authorization header -> Some(Error)
 /// 3. Valid authorization header -> Some(Ok)
-pub fn optionally_auth<T>(req: &Request<T>) -> Option<Result<(i32, String), Response>> {
+pub fn optionally_auth<T: TokenVerifier>(req: &Request<T>) -> Option<Result<(i32, String), ApiError>> {
     if req.header("Authorization").is_some() {
         Some(extract_and_validate_token(req))
     } else {
@@ -51,93 +99,138 @@ pub fn optionally_auth<T>(req: &Request<T>) -> Option<Result<(i32, String), Resp
 }
 
 /// Validates an auth token from a Request, returning the user ID and token if successful
-pub fn extract_and_validate_token<T>(req: &Request<T>) -> Result<(i32, String), Response> {
+pub fn extract_and_validate_token<T: TokenVerifier>(req: &Request<T>) -> Result<(i32, String), ApiError> {
     debug!("Checking for auth header");
-    let auth_header = get_auth_header(&req)
-        .ok_or_else(|| err_response(StatusCode::BadRequest, "Missing Authorization header"))?;
+    let mut auth_headers = get_auth_headers(&req).peekable();
+    if auth_headers.peek().is_none() {
+        return Err(ApiError::MissingAuthHeader);
+    }
+
+    let mut last_err = None;
+    for auth_header in auth_headers {
+        let token = parse_token(auth_header);
 
-    debug!("Extracting token from auth header");
-    let token = parse_token(auth_header);
+        debug!("Authorizing token");
+        match authorize_token(req.state(), &token) {
+            Ok(claims) => {
+                debug!("Token is valid and belongs to user {}", claims.sub);
+                return Ok((claims.sub, token));
+            }
+            Err(e) => last_err = Some(e),
+        }
+    }
 
-    debug!("Authorizing token");
-    let user_id =
-        authorize_token(&token)
-            .map_err(|e| err_response(StatusCode::Forbidden, e.to_string()))?;
+    Err(last_err
+        .expect("checked for at least one auth header above")
+        .into())
+}
 
-    debug!("Token is valid and belongs to user {}", user_id);
+/// Serialize `body` into a `200 OK` JSON response
+pub fn to_json_response<B: Serialize>(body: &B) -> Result<Response, ApiError> {
+    let json = Body::from_json(body).map_err(|e| ApiError::Internal(e.into()))?;
+    let mut resp = Response::new(StatusCode::Ok);
+    resp.set_body(json);
+    Ok(resp)
+}
 
-    Ok((user_id, token))
+/// The single error currency of the API
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("Missing Authorization header")]
+    MissingAuthHeader,
+    #[error("Invalid or expired token")]
+    InvalidToken,
+    #[error("Forbidden")]
+    Forbidden,
+    #[error("Not found")]
+    NotFound,
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Validation(String),
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
 }
 
-/// Converts a serializable payload into a JSON response
-///
-/// If the body cannot be serialized an Err(Response) will be returned with the serialization error
-pub fn to_json_response<B: Serialize>(
-    body: &B,
-    status: StatusCode,
-) -> Response {
-    let mut resp = Response::new(status);
-    match Body::from_json(body) {
-        Ok(json) => {
-            resp.set_body(json);
-            resp
-        }
-        Err(e) => {
-            let error_msg = format!("Failed to serialize response -- {}", e);
-            warn!("{}", error_msg);
-            resp.set_status(StatusCode::InternalServerError);
-            resp.set_body(error_msg);
-            resp
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::MissingAuthHeader => StatusCode::BadRequest,
+            ApiError::InvalidToken | ApiError::Forbidden => StatusCode::Forbidden,
+            ApiError::NotFound => StatusCode::NotFound,
+            ApiError::Conflict(_) => StatusCode::Conflict,
+            ApiError::Validation(_) => StatusCode::BadRequest,
+            ApiError::Internal(_) => StatusCode::InternalServerError,
         }
     }
 }
 
-/// Create an error response payload with the procided Status and message
-pub fn err_response(status: StatusCode, message: impl AsRef<str>) -> Response {
-    let mut resp = Response::new(status);
-    #[derive(Serialize)]
-    struct ErrorResponseBody<'a> {
-        errors: Inner<'a>
+impl From<ApiError> for Response {
+    fn from(e: ApiError) -> Response {
+        if let ApiError::Internal(e) = &e {
+            warn!("internal error: {}", e);
+        }
+
+        #[derive(Serialize)]
+        struct ErrorResponseBody<'a> {
+            errors: Inner<'a>,
+        }
+        #[derive(Serialize)]
+        struct Inner<'a> {
+            body: &'a [&'a str],
+        }
+
+        let message = e.to_string();
+        let payload = ErrorResponseBody {
+            errors: Inner {
+                body: &[message.as_str()],
+            },
+        };
+
+        let mut resp = Response::new(e.status());
+        resp.set_body(Body::from_json(&payload).expect("error body is always serializable"));
+        resp
     }
-    #[derive(Serialize)]
-    struct Inner<'a> {
-        body: &'a [&'a str]
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        self.into()
     }
+}
 
-    let payload = ErrorResponseBody { errors: Inner { body: &[message.as_ref()] } };
-    let body = Body::from_json(&payload).expect("Failed to serialize");
-    resp.set_body(body);
-    resp
+impl From<ProvideError> for ApiError {
+    fn from(e: ProvideError) -> Self {
+        match e {
+            ProvideError::NotFound => ApiError::NotFound,
+            ProvideError::Provider(e) => ApiError::Internal(e.into()),
+            ProvideError::UniqueViolation(details) => ApiError::Conflict(details),
+            ProvideError::ModelViolation(details) => ApiError::Validation(details),
+        }
+    }
+}
 
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        ApiError::Internal(e.into())
+    }
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum ApiError {
-    #[error("Missing Authorization header")]
-    MissingAuthHeader
+impl From<jsonwebtoken::errors::Error> for ApiError {
+    fn from(_: jsonwebtoken::errors::Error) -> Self {
+        ApiError::InvalidToken
+    }
 }
 
-impl From<ProvideError> for Response {
-    /// Convert a ProvideError into a [tide::Response]
-    ///
-    /// This allows the usage of
-    fn from(e: ProvideError) -> Response {
-        let mut resp = Response::new(500);
+impl From<AuthError> for ApiError {
+    fn from(e: AuthError) -> Self {
+        // The client only ever sees "invalid or expired token", but the specific reason is
+        // still worth keeping in the logs.
+        debug!("rejecting token: {}", e);
+
         match e {
-            ProvideError::NotFound => resp.set_status(StatusCode::NotFound),
-            ProvideError::Provider(e) => {
-                resp.set_status(StatusCode::InternalServerError);
-                resp.set_body(e.to_string());
-            }
-            ProvideError::UniqueViolation(details) => {
-                resp.set_status(StatusCode::Conflict);
-                resp.set_body(details)
-            }
-            ProvideError::ModelViolation(details) => {
-                resp.set_status(StatusCode::BadRequest);
-                resp.set_body(details)
-            }
-        };
-        resp
+            AuthError::Jwt(e) => e.into(),
+            AuthError::NotYetValid | AuthError::UnknownKey => ApiError::InvalidToken,
+        }
     }
 }
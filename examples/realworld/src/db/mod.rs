@@ -50,19 +50,92 @@ impl<DB: sqlx::Database> Db for sqlx::Pool<DB> {
     }
 }
 
-/// Create a batch insert statement
+/// The placeholder/upsert dialect to use when generating SQL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Dialect {
+    Postgres,
+    Sqlite,
+}
+
+/// Create a batch insert statement, optionally upserting on a conflict target
 ///
 /// This incantation borrowed from @mehcode
 /// https://discordapp.com/channels/665528275556106240/665528275556106243/694835667401703444
-fn build_batch_insert(rows: usize, columns: usize) -> String {
+///
+/// `columns` gives the column names, in the order each row's values are bound, and its length
+/// must match the number of placeholders emitted per row. When `on_conflict` is `Some`, an
+/// `ON CONFLICT (..) DO UPDATE SET` clause is appended that upserts every column in `columns`.
+fn build_batch_insert(
+    dialect: Dialect,
+    rows: usize,
+    columns: &[&str],
+    on_conflict: Option<&[&str]>,
+) -> String {
     use itertools::Itertools;
 
-    (0..rows)
+    if rows == 0 {
+        return String::new();
+    }
+
+    let num_columns = columns.len();
+
+    let mut sql = (0..rows)
         .format_with(",", |i, f| {
             f(&format_args!(
                 "({})",
-                (1..=columns).format_with(",", |j, f| f(&format_args!("${}", j + (i * columns))))
+                (1..=num_columns).format_with(",", |j, f| match dialect {
+                    Dialect::Postgres => f(&format_args!("${}", j + (i * num_columns))),
+                    Dialect::Sqlite => f(&format_args!("?")),
+                })
             ))
         })
-        .to_string()
+        .to_string();
+
+    if let Some(conflict_columns) = on_conflict {
+        sql.push_str(&format!(
+            " ON CONFLICT ({}) DO UPDATE SET {}",
+            conflict_columns.iter().format(","),
+            columns.iter().format_with(",", |col, f| f(&format_args!(
+                "{} = EXCLUDED.{}",
+                col, col
+            )))
+        ));
+    }
+
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_rows_is_empty() {
+        assert_eq!(build_batch_insert(Dialect::Postgres, 0, &["a", "b"], None), "");
+        assert_eq!(build_batch_insert(Dialect::Sqlite, 0, &["a", "b"], None), "");
+    }
+
+    #[test]
+    fn postgres_numbers_placeholders_across_rows() {
+        assert_eq!(
+            build_batch_insert(Dialect::Postgres, 2, &["a", "b"], None),
+            "($1,$2),($3,$4)"
+        );
+    }
+
+    #[test]
+    fn sqlite_uses_unnumbered_placeholders() {
+        assert_eq!(
+            build_batch_insert(Dialect::Sqlite, 2, &["a", "b"], None),
+            "(?,?),(?,?)"
+        );
+    }
+
+    #[test]
+    fn on_conflict_upserts_every_column() {
+        assert_eq!(
+            build_batch_insert(Dialect::Postgres, 1, &["a", "b"], Some(&["a"])),
+            "($1,$2) ON CONFLICT (a) DO UPDATE SET a = EXCLUDED.a,b = EXCLUDED.b"
+        );
+    }
 }
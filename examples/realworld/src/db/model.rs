@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+
+use crate::api::model::Profile;
+
+/// A profile row as stored in `users`, before a viewer-specific `following` flag is attached
+#[derive(Debug, Clone)]
+pub struct ProfileEntity {
+    pub user_id: i32,
+    pub username: String,
+    pub bio: Option<String>,
+    pub image: Option<String>,
+}
+
+impl From<ProfileEntity> for Profile {
+    fn from(entity: ProfileEntity) -> Self {
+        Profile {
+            username: entity.username,
+            bio: entity.bio,
+            image: entity.image,
+            following: false,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProvideError {
+    #[error("entity not found")]
+    NotFound,
+    #[error(transparent)]
+    Provider(#[from] sqlx::Error),
+    #[error("{0}")]
+    UniqueViolation(String),
+    #[error("{0}")]
+    ModelViolation(String),
+}
+
+/// Abstracts the profile/follow queries the API handlers need, implemented once per backend
+///
+/// See [`crate::db::pg`] and [`crate::db::sqlite`].
+#[async_trait]
+pub trait ProvideData {
+    async fn get_profile_by_username(&mut self, username: &str) -> Result<ProfileEntity, ProvideError>;
+
+    async fn is_following(&mut self, leader_id: i32, follower_id: i32) -> Result<bool, ProvideError>;
+
+    async fn add_follower(&mut self, leader_username: &str, follower_id: i32) -> Result<(), ProvideError>;
+
+    async fn delete_follower(&mut self, leader_username: &str, follower_id: i32) -> Result<(), ProvideError>;
+
+    /// List the profiles following `username`, `viewer_id`'s `following` flag filled in on each
+    async fn get_followers(
+        &mut self,
+        username: &str,
+        viewer_id: Option<i32>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Profile>, i64), ProvideError>;
+
+    /// List the profiles `username` follows, `viewer_id`'s `following` flag filled in on each
+    async fn get_following(
+        &mut self,
+        username: &str,
+        viewer_id: Option<i32>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Profile>, i64), ProvideError>;
+}
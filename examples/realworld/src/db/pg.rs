@@ -0,0 +1,149 @@
+use async_trait::async_trait;
+use sqlx::postgres::Postgres;
+use sqlx::{Row, Transaction};
+
+use crate::api::model::Profile;
+use crate::db::model::{ProfileEntity, ProvideData, ProvideError};
+
+enum Relationship {
+    Followers,
+    Following,
+}
+
+#[async_trait]
+impl<'c> ProvideData for Transaction<'c, Postgres> {
+    async fn get_profile_by_username(&mut self, username: &str) -> Result<ProfileEntity, ProvideError> {
+        sqlx::query_as!(
+            ProfileEntity,
+            "SELECT user_id, username, bio, image FROM users WHERE username = $1",
+            username
+        )
+        .fetch_optional(self)
+        .await?
+        .ok_or(ProvideError::NotFound)
+    }
+
+    async fn is_following(&mut self, leader_id: i32, follower_id: i32) -> Result<bool, ProvideError> {
+        let row = sqlx::query!(
+            "SELECT EXISTS(SELECT 1 FROM followers WHERE leader_id = $1 AND follower_id = $2) AS \"following!\"",
+            leader_id,
+            follower_id
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(row.following)
+    }
+
+    async fn add_follower(&mut self, leader_username: &str, follower_id: i32) -> Result<(), ProvideError> {
+        sqlx::query!(
+            "INSERT INTO followers (leader_id, follower_id)
+             SELECT user_id, $2 FROM users WHERE username = $1
+             ON CONFLICT (leader_id, follower_id) DO NOTHING",
+            leader_username,
+            follower_id
+        )
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_follower(&mut self, leader_username: &str, follower_id: i32) -> Result<(), ProvideError> {
+        sqlx::query!(
+            "DELETE FROM followers USING users
+             WHERE followers.leader_id = users.user_id
+             AND users.username = $1 AND followers.follower_id = $2",
+            leader_username,
+            follower_id
+        )
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_followers(
+        &mut self,
+        username: &str,
+        viewer_id: Option<i32>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Profile>, i64), ProvideError> {
+        fetch_relationship(self, username, viewer_id, limit, offset, Relationship::Followers).await
+    }
+
+    async fn get_following(
+        &mut self,
+        username: &str,
+        viewer_id: Option<i32>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<Profile>, i64), ProvideError> {
+        fetch_relationship(self, username, viewer_id, limit, offset, Relationship::Following).await
+    }
+}
+
+/// Shared implementation of the follower/following listings
+async fn fetch_relationship(
+    tx: &mut Transaction<'_, Postgres>,
+    username: &str,
+    viewer_id: Option<i32>,
+    limit: i64,
+    offset: i64,
+    relationship: Relationship,
+) -> Result<(Vec<Profile>, i64), ProvideError> {
+    let (from_col, to_col) = match relationship {
+        Relationship::Followers => ("follower_id", "leader_id"),
+        Relationship::Following => ("leader_id", "follower_id"),
+    };
+
+    let count_query = format!(
+        "SELECT count(*) AS total
+         FROM users u
+         JOIN followers f ON f.{from_col} = u.user_id
+         JOIN users target ON target.user_id = f.{to_col}
+         WHERE target.username = $1",
+        from_col = from_col,
+        to_col = to_col,
+    );
+
+    let total: i64 = sqlx::query(&count_query)
+        .bind(username)
+        .fetch_one(&mut *tx)
+        .await?
+        .get("total");
+
+    let query = format!(
+        "SELECT u.username, u.bio, u.image,
+                EXISTS(SELECT 1 FROM followers v WHERE v.leader_id = u.user_id AND v.follower_id = $2) AS following
+         FROM users u
+         JOIN followers f ON f.{from_col} = u.user_id
+         JOIN users target ON target.user_id = f.{to_col}
+         WHERE target.username = $1
+         ORDER BY u.username
+         LIMIT $3 OFFSET $4",
+        from_col = from_col,
+        to_col = to_col,
+    );
+
+    let rows = sqlx::query(&query)
+        .bind(username)
+        .bind(viewer_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(tx)
+        .await?;
+
+    let profiles = rows
+        .into_iter()
+        .map(|row| Profile {
+            username: row.get("username"),
+            bio: row.get("bio"),
+            image: row.get("image"),
+            following: row.get("following"),
+        })
+        .collect();
+
+    Ok((profiles, total))
+}
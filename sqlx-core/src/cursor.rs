@@ -1,4 +1,9 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
 use futures_core::future::BoxFuture;
+use futures_core::stream::{BoxStream, Stream};
+use futures_util::{stream, TryStreamExt};
 
 use crate::database::{Database, HasRow};
 use crate::executor::Execute;
@@ -36,3 +41,74 @@ where
         &'cur mut self,
     ) -> BoxFuture<'cur, crate::Result<Self::Database, Option<<Self::Database as HasRow<'cur>>::Row>>>;
 }
+
+/// A [`Stream`] of values decoded from a [`Cursor`] by a caller-supplied closure
+///
+/// Returned by [`CursorExt::map_row`].
+pub struct MapRow<'c, DB: Database, T> {
+    inner: BoxStream<'c, crate::Result<DB, T>>,
+}
+
+impl<'c, DB: Database, T> MapRow<'c, DB, T> {
+    fn new<'q, C, F>(cursor: C, mut f: F) -> Self
+    where
+        C: Cursor<'c, 'q, Database = DB> + Send + 'c,
+        F: for<'r> FnMut(<DB as HasRow<'r>>::Row) -> T + Send + 'c,
+        T: Send + 'c,
+    {
+        let inner = stream::unfold(Some(cursor), move |cursor| {
+            let f = &mut f;
+
+            async move {
+                let mut cursor = cursor?;
+
+                match cursor.next().await {
+                    Ok(Some(row)) => Some((Ok(f(row)), Some(cursor))),
+                    Ok(None) => None,
+                    Err(e) => Some((Err(e), None)),
+                }
+            }
+        });
+
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+
+    /// Fetch the next item, erroring if the cursor is already exhausted
+    pub async fn fetch_one(mut self) -> crate::Result<DB, T> {
+        self.fetch_optional()
+            .await?
+            .ok_or(crate::Error::RowNotFound)
+    }
+
+    /// Fetch the next item, if any
+    pub async fn fetch_optional(&mut self) -> crate::Result<DB, Option<T>> {
+        self.inner.try_next().await
+    }
+}
+
+impl<'c, DB: Database, T> Stream for MapRow<'c, DB, T> {
+    type Item = crate::Result<DB, T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.as_mut().poll_next(cx)
+    }
+}
+
+/// Extension methods that adapt a [`Cursor`] into the `futures`/[`TryStreamExt`] ecosystem
+pub trait CursorExt<'c, 'q>: Cursor<'c, 'q> {
+    /// Adapt this cursor into a [`Stream`], decoding each row with `f`
+    ///
+    /// e.g. `tx.fetch(query).map_row(f).try_collect::<Vec<_>>().await?`
+    fn map_row<T, F>(self, f: F) -> MapRow<'c, Self::Database, T>
+    where
+        Self: Sized + Send + 'c,
+        T: Send + 'c,
+        F: for<'r> FnMut(<Self::Database as HasRow<'r>>::Row) -> T + Send + 'c,
+    {
+        MapRow::new(self, f)
+    }
+}
+
+impl<'c, 'q, C: Cursor<'c, 'q>> CursorExt<'c, 'q> for C {}